@@ -0,0 +1,187 @@
+//! Procedural macro support for `pathetic`.
+//!
+//! This crate backs `pathetic::uri!` and isn't meant to be depended on
+//! directly; always go through the re-export in `pathetic`.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, LitStr, Token};
+
+/// One path segment of a `uri!` template: a literal to validate at compile
+/// time, or an expression to encode at runtime.
+enum PathPart {
+    Literal(LitStr),
+    Expr(Expr),
+}
+
+/// One `"key" = value` query pair of a `uri!` template. The key is always a
+/// literal; only the value is a runtime expression.
+struct QueryPart {
+    key: LitStr,
+    value: Expr,
+}
+
+struct UriTemplate {
+    path: Vec<PathPart>,
+    query: Vec<QueryPart>,
+}
+
+impl Parse for UriTemplate {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut path = vec![parse_path_part(input)?];
+
+        while input.peek(Token![/]) {
+            input.parse::<Token![/]>()?;
+            path.push(parse_path_part(input)?);
+        }
+
+        let mut query = Vec::new();
+        if input.peek(Token![?]) {
+            input.parse::<Token![?]>()?;
+            loop {
+                let key: LitStr = input.parse()?;
+                input.parse::<Token![=]>()?;
+                let value: Expr = input.parse()?;
+                query.push(QueryPart { key, value });
+
+                if input.peek(Token![,]) {
+                    input.parse::<Token![,]>()?;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        if !input.is_empty() {
+            return Err(input.error("unexpected tokens in `uri!` template"));
+        }
+
+        Ok(UriTemplate { path, query })
+    }
+}
+
+fn parse_path_part(input: ParseStream) -> syn::Result<PathPart> {
+    if input.peek(LitStr) {
+        Ok(PathPart::Literal(input.parse()?))
+    } else {
+        Ok(PathPart::Expr(parse_bounded_expr(input)?))
+    }
+}
+
+/// Parse an expression bounded to the tokens up to (but not including) the
+/// next top-level `/`, `?` or `,`, rather than `Expr::parse`'s full
+/// expression grammar.
+///
+/// `/` and `?` are both template separators that also read as valid infix
+/// (division) or postfix (try) operators, so letting `Expr::parse` run to
+/// its usual full precedence would greedily swallow the rest of the
+/// template into a single expression. Collecting tokens one at a time stops
+/// cleanly at a separator while still treating a parenthesized/bracketed
+/// group as one atomic token, so `(x / y)` or `foo.bar()` parse as intended.
+fn parse_bounded_expr(input: ParseStream) -> syn::Result<Expr> {
+    let mut tokens = proc_macro2::TokenStream::new();
+
+    while !input.is_empty()
+        && !input.peek(Token![/])
+        && !input.peek(Token![?])
+        && !input.peek(Token![,])
+    {
+        let tt: proc_macro2::TokenTree = input.parse()?;
+        tokens.extend(std::iter::once(tt));
+    }
+
+    if tokens.is_empty() {
+        return Err(input.error("expected an expression"));
+    }
+
+    syn::parse2(tokens)
+}
+
+/// Validate the template's static skeleton (literals only, expressions
+/// stubbed out) against the same `http://_` base `Uri::new` uses, so a
+/// malformed literal segment or query key is a compile error.
+fn validate_skeleton(template: &UriTemplate) -> syn::Result<()> {
+    let mut skeleton = String::new();
+    for part in &template.path {
+        skeleton.push('/');
+        match part {
+            PathPart::Literal(lit) => skeleton.push_str(&lit.value()),
+            PathPart::Expr(_) => skeleton.push('_'),
+        }
+    }
+    for (i, part) in template.query.iter().enumerate() {
+        skeleton.push(if i == 0 { '?' } else { '&' });
+        skeleton.push_str(&part.key.value());
+        skeleton.push_str("=_");
+    }
+
+    url::Url::parse("http://_")
+        .expect("`http://_` is a valid `URL`")
+        .join(&skeleton)
+        .map(|_| ())
+        .map_err(|err| {
+            syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!("invalid `uri!` template: {}", err),
+            )
+        })
+}
+
+/// Build a `pathetic::Uri` from a literal template, percent-encoding every
+/// interpolated segment and query value through the same encoders as
+/// `with_path_segments_mut`/`with_query_pairs_mut`.
+///
+/// See `pathetic::uri!` for the syntax and examples.
+#[proc_macro]
+pub fn uri(input: TokenStream) -> TokenStream {
+    let template = syn::parse_macro_input!(input as UriTemplate);
+
+    if let Err(err) = validate_skeleton(&template) {
+        return err.to_compile_error().into();
+    }
+
+    let path_pushes = template.path.iter().map(|part| match part {
+        PathPart::Literal(lit) => quote! {
+            __pathetic_segments.push(#lit);
+        },
+        PathPart::Expr(expr) => quote! {
+            __pathetic_segments.push(&::std::string::ToString::to_string(&(#expr)));
+        },
+    });
+
+    let query_pushes = template.query.iter().map(|part| {
+        let key = &part.key;
+        let value = &part.value;
+        quote! {
+            __pathetic_query.append_pair(#key, &::std::string::ToString::to_string(&(#value)));
+        }
+    });
+
+    let query_block = if template.query.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            {
+                let mut __pathetic_query = __pathetic_uri.query_pairs_mut();
+                #(#query_pushes)*
+            }
+        }
+    };
+
+    let expanded = quote! {
+        {
+            let mut __pathetic_uri = ::pathetic::uri();
+            {
+                let mut __pathetic_segments = __pathetic_uri.path_segments_mut();
+                #(#path_pushes)*
+            }
+            #query_block
+            __pathetic_uri
+        }
+    };
+
+    expanded.into()
+}
@@ -18,9 +18,44 @@ pub fn uri() -> Uri {
     Uri::default()
 }
 
-/// The relative URI container
+/// Build a [`Uri`] from a literal template with interpolated, percent-encoded
+/// path segments and query values. The static parts of the template are
+/// checked against the same `http://_` base `Uri::new` uses, so a malformed
+/// literal is a compile error rather than a runtime one.
+///
+/// Segments are separated by `/`, with each literal given as a plain segment
+/// name (no leading or embedded `/`, or it would itself be percent-encoded
+/// as one segment). The query section starts with a single `?`, and
+/// `"key" = value` pairs after that are separated by `,` rather than a
+/// repeated `?`: `?` also reads as Rust's postfix try operator, so a second
+/// `?` would be parsed as part of the previous pair's value expression
+/// instead of introducing a new pair.
+///
+/// ```rust
+/// # fn main() {
+/// let id = 42;
+/// let uri = pathetic::uri!("users" / id / "posts" ? "page" = 1, "q" = "rust");
+/// assert_eq!("/users/42/posts?page=1&q=rust", uri.as_str());
+/// # }
+/// ```
+pub use pathetic_macros::uri;
+
+/// Whether a [`Uri`] was parsed from a relative reference or carries its own
+/// scheme and authority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    /// Parsed under the `http://_` base; `as_str()` serializes from the path on.
+    Relative,
+    /// Parsed as a standalone URI; `as_str()` serializes the full URI.
+    Absolute,
+}
+
+/// The relative-or-absolute URI container
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Uri(url::Url);
+pub struct Uri {
+    url: url::Url,
+    kind: Kind,
+}
 
 impl std::fmt::Debug for Uri {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -41,6 +76,27 @@ impl std::convert::TryFrom<&str> for Uri {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Uri {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Uri {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        Uri::new(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(feature = "actix-web")]
 impl From<&actix_web::http::uri::Uri> for Uri {
     fn from(t: &actix_web::http::uri::Uri) -> Self {
@@ -58,81 +114,168 @@ impl AsRef<str> for Uri {
 
 impl Default for Uri {
     fn default() -> Self {
-        Self(Self::base_url())
+        Self { url: Self::base_url(), kind: Kind::Relative }
     }
 }
 
 impl Uri {
     fn base_url() -> url::Url {
-        use once_cell::sync::Lazy; 
+        use once_cell::sync::Lazy;
         static URL: Lazy<url::Url> =
             Lazy::new(|| "http://_".parse().expect("`http://_` is a valid `URL`"));
         URL.clone()
     }
 
-    /// Create a new `Uri`, from a path-query-fragment `str`.
+    /// Create a new `Uri`, from a path-query-fragment `str` or a full,
+    /// scheme-and-authority-carrying URI.
+    ///
+    /// A relative input (e.g. `/foo?bar`) is parsed against the fake
+    /// `http://_` base, as before, and keeps serializing relatively. An
+    /// absolute input (e.g. `https://example.com/foo`) is parsed on its own
+    /// and keeps its scheme and authority; see [`scheme`](Self::scheme),
+    /// [`host`](Self::host), [`port`](Self::port) and
+    /// [`resolve_against`](Self::resolve_against).
     pub fn new(input: &str) -> Result<Self, url::ParseError> {
-        Self::base_url().join(input).map(Self)
+        match url::Url::parse(input) {
+            Ok(url) => Ok(Self { url, kind: Kind::Absolute }),
+            Err(url::ParseError::RelativeUrlWithoutBase) => {
+                Self::base_url().join(input).map(|url| Self { url, kind: Kind::Relative })
+            }
+            Err(err) => Err(err),
+        }
     }
 
     /// Parse a string as an URL, with this URL as the base URL.
+    ///
+    /// If `input` is itself an absolute URI, the result is absolute;
+    /// otherwise the result keeps this `Uri`'s kind.
     pub fn join(&self, input: &str) -> Result<Self, url::ParseError> {
-        self.0.join(input).map(Self)
+        let kind = match url::Url::parse(input) {
+            Ok(_) => Kind::Absolute,
+            Err(url::ParseError::RelativeUrlWithoutBase) => self.kind,
+            Err(err) => return Err(err),
+        };
+        self.url.join(input).map(|url| Self { url, kind })
     }
 
-    /// Return the serialization of this URL.    
+    /// Return the serialization of this URI: the full URI if absolute, or
+    /// the path-query-fragment portion if relative.
     pub fn as_str(&self) -> &str {
-        &self.0[url::Position::BeforePath..]
-    }    
+        match self.kind {
+            Kind::Relative => &self.url[url::Position::BeforePath..],
+            Kind::Absolute => self.url.as_str(),
+        }
+    }
+
+    /// Return this URI's scheme, if it's absolute.
+    pub fn scheme(&self) -> Option<&str> {
+        match self.kind {
+            Kind::Absolute => Some(self.url.scheme()),
+            Kind::Relative => None,
+        }
+    }
+
+    /// Return this URI's host, if it's absolute and has one.
+    pub fn host(&self) -> Option<&str> {
+        match self.kind {
+            Kind::Absolute => self.url.host_str(),
+            Kind::Relative => None,
+        }
+    }
+
+    /// Return this URI's port, if it's absolute and one was given explicitly.
+    pub fn port(&self) -> Option<u16> {
+        match self.kind {
+            Kind::Absolute => self.url.port(),
+            Kind::Relative => None,
+        }
+    }
+
+    /// Resolve this `Uri` against `base`, per RFC 3986 reference resolution.
+    ///
+    /// If this `Uri` is already absolute, a clone of its own `Url` is
+    /// returned and `base` is ignored, matching RFC 3986's rule that an
+    /// absolute reference resolves to itself.
+    pub fn resolve_against(&self, base: &url::Url) -> url::Url {
+        match self.kind {
+            Kind::Absolute => self.url.clone(),
+            Kind::Relative => base
+                .join(self.as_str())
+                .expect("a relative `Uri`'s serialization always joins onto a valid base"),
+        }
+    }
 
     /// Return the path for this URL, as a percent-encoded ASCII string.
     pub fn path(&self) -> &str {
-        self.0.path()
+        self.url.path()
     }
 
     /// Return this URL's query string, if any, as a percent-encoded ASCII string.
     pub fn query(&self) -> Option<&str> {
-        self.0.query()
+        self.url.query()
     }
 
     /// Return this URL's fragment identifier, if any.
     pub fn fragment(&self) -> Option<&str> {
-        self.0.fragment()
+        self.url.fragment()
     }
 
     /// Return an iterator of '/' slash-separated path segments, each as a percent-encoded ASCII string.
     pub fn path_segments(&self) -> std::str::Split<char> {
-        self.0.path_segments().expect("`Uri` is always-a-base")
+        self.url.path_segments().expect("`Uri` is always-a-base")
     }
 
     /// Return an object with methods to manipulate this URL's path segments.
     pub fn path_segments_mut(&mut self) -> url::PathSegmentsMut {
-        self.0.path_segments_mut().expect("`Uri` is always-a-base")
+        self.url.path_segments_mut().expect("`Uri` is always-a-base")
     }
 
     /// Parse the URL's query string, if any, as application/x-www-form-urlencoded and return an iterator of (key, value) pairs.
     pub fn query_pairs(&self) -> url::form_urlencoded::Parse {
-        self.0.query_pairs()
+        self.url.query_pairs()
     }
 
     /// Manipulate this URL's query string, viewed as a sequence of name/value pairs in application/x-www-form-urlencoded syntax.
     pub fn query_pairs_mut(&mut self) -> url::form_urlencoded::Serializer<url::UrlQuery> {
-        self.0.query_pairs_mut()
+        self.url.query_pairs_mut()
+    }
+
+    /// Deserialize this `Uri`'s query string as `application/x-www-form-urlencoded`
+    /// into `T`, as a typed alternative to [`query_pairs`](Self::query_pairs).
+    #[cfg(feature = "serde")]
+    pub fn query_as<T>(&self) -> Result<T, serde_urlencoded::de::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        serde_urlencoded::from_str(self.query().unwrap_or(""))
+    }
+
+    /// Serialize `value` as `application/x-www-form-urlencoded` and set it as
+    /// this `Uri`'s query string, as a typed alternative to
+    /// [`query_pairs_mut`](Self::query_pairs_mut).
+    #[cfg(feature = "serde")]
+    pub fn set_query_from<T>(&mut self, value: &T) -> Result<(), serde_urlencoded::ser::Error>
+    where
+        T: serde::Serialize,
+    {
+        let encoded = serde_urlencoded::to_string(value)?;
+        self.set_query(Some(&encoded));
+        Ok(())
     }
 
     /// Change this URL's path.
     pub fn set_path(&mut self, path: &str) {
-        self.0.set_path(path)
+        self.url.set_path(path)
     }
 
     /// Change this URL's query string.
     pub fn set_query(&mut self, query: Option<&str>) {
-        self.0.set_query(query)
+        self.url.set_query(query)
     }
 
     /// Change this URL's fragment identifier.
     pub fn set_fragment(&mut self, fragment: Option<&str>) {
-        self.0.set_fragment(fragment)
+        self.url.set_fragment(fragment)
     }
 
     /// Modify the path inline.
@@ -153,6 +296,82 @@ impl Uri {
         self
     }
 
+    /// Concatenate `base`'s path segments ahead of this `Uri`'s, returning
+    /// a new `Uri` that nests this one under `base`.
+    ///
+    /// Unlike [`join`](Self::join), which delegates to `url`'s base-join
+    /// semantics and replaces an absolute path outright, `prefix` always
+    /// prepends: duplicate `/` separators are collapsed, a trailing empty
+    /// segment on `base` (i.e. a trailing slash) is normalized away, and
+    /// this `Uri`'s own query and fragment are preserved untouched.
+    pub fn prefix(&self, base: &Uri) -> Uri {
+        let base_segments: Vec<String> = base
+            .path_segments()
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| percent_decode(segment).into_owned())
+            .collect();
+        let self_segments: Vec<String> = self
+            .path_segments()
+            .map(|segment| percent_decode(segment).into_owned())
+            .collect();
+
+        let mut out = self.clone();
+        {
+            let mut segments_mut = out.path_segments_mut();
+            segments_mut.clear();
+            segments_mut.extend(base_segments.iter().map(String::as_str));
+            segments_mut.extend(self_segments.iter().map(String::as_str));
+        }
+        out
+    }
+
+    /// Nest this `Uri` under a route prefix given as a plain path string, as
+    /// in `uri.mount_under("/api/v1")`.
+    ///
+    /// Equivalent to `self.prefix(&Uri::default().with_path(prefix))`.
+    pub fn mount_under(&self, prefix: &str) -> Uri {
+        self.prefix(&Uri::default().with_path(prefix))
+    }
+
+    /// Append `segments` to this `Uri`'s path in place.
+    pub fn push_segments(&mut self, segments: &[&str]) {
+        self.path_segments_mut().extend(segments);
+    }
+
+    /// Return a new `Uri` with the RFC 3986 `remove_dot_segments` algorithm
+    /// applied to its path, resolving `.` and `..` segments.
+    pub fn normalize(&self) -> Uri {
+        let normalized = remove_dot_segments(self.path());
+        self.clone().with_path(&normalized)
+    }
+
+    /// Decode this `Uri`'s path segments into an OS path, suitable for
+    /// joining onto a root directory to serve a request.
+    ///
+    /// Returns `None` if [`is_safe`](Self::is_safe) rejects any segment, so
+    /// callers never need to handle traversal outside the root themselves.
+    pub fn to_path(&self) -> Option<std::path::PathBuf> {
+        if !self.is_safe() {
+            return None;
+        }
+
+        let mut buf = std::path::PathBuf::new();
+        for segment in self.path_segments() {
+            buf.push(&*percent_decode(segment));
+        }
+        Some(buf)
+    }
+
+    /// Check whether every segment of this `Uri`'s path is safe to decode
+    /// and join onto a root directory, without allocating a `PathBuf`.
+    ///
+    /// A segment is unsafe if, once percent-decoded, it is empty, is `.` or
+    /// `..`, contains a path separator or a NUL byte, or (on Windows) is a
+    /// reserved device name.
+    pub fn is_safe(&self) -> bool {
+        self.path_segments().all(|segment| is_safe_segment(&percent_decode(segment)))
+    }
+
     /// Modify the path segments inline.
     pub fn with_path_segments_mut<F>(mut self, cls: F) -> Self
     where
@@ -181,6 +400,85 @@ impl Uri {
     }
 }
 
+/// Percent-decode a single path segment.
+fn percent_decode(segment: &str) -> std::borrow::Cow<str> {
+    percent_encoding::percent_decode_str(segment).decode_utf8_lossy()
+}
+
+/// Windows reserved device names, checked case-insensitively against a
+/// segment with any extension stripped.
+#[cfg(windows)]
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn is_safe_segment(segment: &str) -> bool {
+    if segment.is_empty() || segment == "." || segment == ".." {
+        return false;
+    }
+
+    if segment.contains('/') || segment.contains('\\') || segment.contains('\0') {
+        return false;
+    }
+
+    #[cfg(windows)]
+    {
+        let name = segment.split('.').next().unwrap_or(segment).to_ascii_uppercase();
+        if RESERVED_NAMES.contains(&name.as_str()) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// The last segment of `output`, including its preceding `/` if any, is
+/// removed; used by [`remove_dot_segments`] when it encounters `/../`.
+fn pop_last_segment(output: &mut String) {
+    match output.rfind('/') {
+        Some(index) => output.truncate(index),
+        None => output.clear(),
+    }
+}
+
+/// Apply the RFC 3986 §5.2.4 `remove_dot_segments` algorithm to an
+/// already-percent-encoded path.
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path.to_string();
+    let mut output = String::new();
+
+    while !input.is_empty() {
+        if let Some(rest) = input.strip_prefix("../") {
+            input = rest.to_string();
+        } else if let Some(rest) = input.strip_prefix("./") {
+            input = rest.to_string();
+        } else if let Some(rest) = input.strip_prefix("/./") {
+            input = format!("/{}", rest);
+        } else if input == "/." {
+            input = "/".to_string();
+        } else if let Some(rest) = input.strip_prefix("/../") {
+            input = format!("/{}", rest);
+            pop_last_segment(&mut output);
+        } else if input == "/.." {
+            input = "/".to_string();
+            pop_last_segment(&mut output);
+        } else if input == "." || input == ".." {
+            input = String::new();
+        } else {
+            let segment_end = if input.starts_with('/') {
+                input[1..].find('/').map_or(input.len(), |i| i + 1)
+            } else {
+                input.find('/').unwrap_or(input.len())
+            };
+            output.push_str(&input[..segment_end]);
+            input = input[segment_end..].to_string();
+        }
+    }
+
+    output
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -234,4 +532,113 @@ mod tests {
         assert_eq!("/a/", uri.as_str());
 
     }
+
+    #[test]
+    fn absolute_uris_round_trip() {
+
+        let uri = Uri::new("https://example.com:8080/foo/bar?baz=qux").unwrap();
+
+        assert_eq!("https://example.com:8080/foo/bar?baz=qux", uri.as_str());
+        assert_eq!(Some("https"), uri.scheme());
+        assert_eq!(Some("example.com"), uri.host());
+        assert_eq!(Some(8080), uri.port());
+
+        let uri = Uri::new("/foo/bar?baz=qux").unwrap();
+
+        assert_eq!(None, uri.scheme());
+        assert_eq!(None, uri.host());
+        assert_eq!(None, uri.port());
+
+        let base: url::Url = "https://example.com/a/b/".parse().unwrap();
+
+        assert_eq!(
+            "https://example.com/foo/bar?baz=qux",
+            uri.resolve_against(&base).as_str()
+        );
+
+        let absolute = Uri::new("https://other.example/x").unwrap();
+
+        assert_eq!("https://other.example/x", absolute.resolve_against(&base).as_str());
+    }
+
+    #[test]
+    fn prefix_mount_under_and_push_segments() {
+
+        let route = Uri::new("/posts/1?page=2#comments").unwrap();
+        let base = Uri::new("/api/v1/").unwrap();
+
+        let mounted = route.prefix(&base);
+
+        assert_eq!("/api/v1/posts/1?page=2#comments", mounted.as_str());
+
+        let mounted = route.mount_under("/api/v1/");
+
+        assert_eq!("/api/v1/posts/1?page=2#comments", mounted.as_str());
+
+        // Segments carrying percent-escapes must not be re-encoded when prefixed.
+        let route = Uri::new("/a%20b").unwrap();
+        let base = Uri::new("/c%2Fd").unwrap();
+
+        assert_eq!("/c%2Fd/a%20b", route.prefix(&base).as_str());
+
+        let mut uri = Uri::new("/foo").unwrap();
+
+        uri.push_segments(&["bar", "baz"]);
+
+        assert_eq!("/foo/bar/baz", uri.as_str());
+    }
+
+    #[test]
+    fn normalize_to_path_and_is_safe() {
+
+        assert_eq!("/a/g", remove_dot_segments("/a/b/c/./../../g"));
+        assert_eq!("mid/6", remove_dot_segments("mid/content=5/../6"));
+
+        let mut uri = Uri::default();
+        uri.set_path("/a/b/c/./../../g");
+
+        assert_eq!("/a/g", uri.normalize().path());
+
+        let uri = Uri::new("/users/42/draft%20post.md").unwrap();
+
+        assert_eq!(
+            std::path::PathBuf::from("users").join("42").join("draft post.md"),
+            uri.to_path().unwrap()
+        );
+
+        assert!(Uri::new("/foo/bar").unwrap().is_safe());
+        assert!(!Uri::new("/foo/..").unwrap().is_safe());
+        assert!(!Uri::new("/foo/%2e%2e").unwrap().is_safe());
+        assert!(!Uri::new("/foo/a%2Fb").unwrap().is_safe());
+        assert!(Uri::new("/foo/..").unwrap().to_path().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_and_typed_query() {
+
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Filter {
+            page: u32,
+            q: String,
+        }
+
+        let uri = Uri::new("/search?page=2&q=rust").unwrap();
+
+        assert_eq!(Filter { page: 2, q: "rust".into() }, uri.query_as().unwrap());
+
+        let mut uri = Uri::new("/search").unwrap();
+
+        uri.set_query_from(&Filter { page: 3, q: "pathetic".into() }).unwrap();
+
+        assert_eq!("/search?page=3&q=pathetic", uri.as_str());
+
+        let json = serde_json::to_string(&uri).unwrap();
+
+        assert_eq!("\"/search?page=3&q=pathetic\"", json);
+
+        let round_tripped: Uri = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(uri, round_tripped);
+    }
 }